@@ -1,5 +1,6 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
 
 #[wasm_bindgen]
 extern "C" {
@@ -27,6 +28,27 @@ pub struct TableData {
 pub struct CompareOptions {
     pub trim: bool,
     pub case_insensitive: bool,
+    /// Opt-in fuzzy key matching for left keys with no exact right-side match.
+    #[serde(default)]
+    pub fuzzy: bool,
+    /// When true, scale the allowed edit distance with key length
+    /// (0 typos for <=4 chars, 1 for 5-8, 2 for >8). When false, a fixed
+    /// threshold of 1 typo is used regardless of key length.
+    #[serde(default)]
+    pub max_typos_auto: bool,
+    /// Apply Unicode NFKC normalization, folding compatibility characters
+    /// (e.g. full-width digits/letters, half-width katakana) together.
+    #[serde(default)]
+    pub nfkc: bool,
+    /// Collapse any run of whitespace (including embedded `\r\n`/`\r` from
+    /// pasted cells) into a single space, after trimming.
+    #[serde(default)]
+    pub collapse_whitespace: bool,
+    /// Fold zenkaku<->hankaku ASCII/kana width variants together. NFKC
+    /// already covers most of this, but this flag applies it even when
+    /// `nfkc` is off, without NFKC's broader compatibility decomposition.
+    #[serde(default)]
+    pub fold_width: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,8 +57,28 @@ pub struct CompareInput {
     pub left_rows: Vec<Vec<String>>,
     pub right_headers: Vec<String>,
     pub right_rows: Vec<Vec<String>>,
+    /// Single-column key, kept for back-compat. Ignored when `keys` is set.
+    #[serde(default)]
     pub key: String,
+    /// Composite key columns, matched in order. Falls back to `key` when empty.
+    #[serde(default)]
+    pub keys: Vec<String>,
     pub options: CompareOptions,
+    /// How to coalesce matched rows into `CompareOutput::merged`. One of
+    /// `prefer_left`, `prefer_right`, `prefer_non_empty`, or
+    /// `prefer_newer_by:<col>`. Leave unset to skip the merged table.
+    #[serde(default)]
+    pub merge_strategy: Option<String>,
+}
+
+impl CompareInput {
+    fn key_columns(&self) -> Vec<String> {
+        if !self.keys.is_empty() {
+            self.keys.clone()
+        } else {
+            vec![self.key.clone()]
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,14 +87,59 @@ pub struct CompareOutput {
     pub left_only: TableData,
     pub right_only: TableData,
     pub duplicates: TableData,
+    /// Single-column-per-field reconciled table (empty when `merge_strategy`
+    /// wasn't set). See `MergeStrategy`.
+    pub merged: TableData,
     pub log: Vec<(String, String)>,
 }
 
+/// How to resolve a shared column when coalescing a matched row pair into
+/// `CompareOutput::merged`.
+#[allow(clippy::enum_variant_names)]
+enum MergeStrategy {
+    PreferLeft,
+    PreferRight,
+    PreferNonEmpty,
+    /// Compares the named column's value on each side and takes every shared
+    /// column from whichever side sorts later (the left side on a tie);
+    /// columns present on only one side still come from that side. Plain
+    /// string comparison, so
+    /// this works directly for ISO-8601-style date columns but not for
+    /// arbitrary date formats.
+    PreferNewerBy(String),
+}
+
+impl MergeStrategy {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "prefer_left" => Some(MergeStrategy::PreferLeft),
+            "prefer_right" => Some(MergeStrategy::PreferRight),
+            "prefer_non_empty" => Some(MergeStrategy::PreferNonEmpty),
+            _ => s.strip_prefix("prefer_newer_by:").map(|col| MergeStrategy::PreferNewerBy(col.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SplitInput {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<String>>,
+    /// Single-column key, kept for back-compat. Ignored when `keys` is set.
+    #[serde(default)]
     pub key: String,
+    /// Composite key columns, matched in order. Falls back to `key` when empty.
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+impl SplitInput {
+    fn key_columns(&self) -> Vec<String> {
+        if !self.keys.is_empty() {
+            self.keys.clone()
+        } else {
+            vec![self.key.clone()]
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -66,44 +153,481 @@ pub struct SplitOutput {
     pub parts: Vec<SplitPart>,
 }
 
+/// Number of trailing metadata columns appended to `result_headers`
+/// after the L__/R__ prefixed columns (match_status, diff_cols,
+/// dup_key_flag, key_distance, ambiguous_flag).
+const META_COL_COUNT: usize = 5;
+
+/// Separator joining normalized per-column values into one composite
+/// lookup key. A control character, so it can't collide with cell text.
+const KEY_SEPARATOR: char = '\u{1}';
+
+/// Error surfaced to the front end as a `{ "ok": false, "error": ... }`
+/// envelope instead of a WASM panic, so a malformed payload or a typo in a
+/// key name can be shown to the user rather than aborting the module.
+#[derive(Debug)]
+enum AppError {
+    BadJson(String),
+    EmptyInput(String),
+    KeyNotFound { side: String, key: String, available_headers: Vec<String> },
+    RaggedRows(String),
+}
+
+type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::BadJson(_) => "BAD_JSON",
+            AppError::EmptyInput(_) => "EMPTY_INPUT",
+            AppError::KeyNotFound { .. } => "KEY_NOT_FOUND",
+            AppError::RaggedRows(_) => "RAGGED_ROWS",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            AppError::BadJson(msg) => format!("Failed to parse input JSON: {}", msg),
+            AppError::EmptyInput(msg) => msg.clone(),
+            AppError::KeyNotFound { side, key, .. } => format!("Key column \"{}\" not found in {} headers", key, side),
+            AppError::RaggedRows(msg) => msg.clone(),
+        }
+    }
+
+    fn to_payload(&self) -> AppErrorPayload {
+        let available_headers = match self {
+            AppError::KeyNotFound { available_headers, .. } => Some(available_headers.clone()),
+            _ => None,
+        };
+        AppErrorPayload {
+            code: self.code().to_string(),
+            message: self.message(),
+            available_headers,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AppErrorPayload {
+    code: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    available_headers: Option<Vec<String>>,
+}
+
+fn ok_envelope<T: Serialize>(data: &T) -> String {
+    serde_json::to_string(&serde_json::json!({ "ok": true, "data": data }))
+        .expect("Failed to serialize success envelope")
+}
+
+fn err_envelope(err: AppError) -> String {
+    serde_json::to_string(&serde_json::json!({ "ok": false, "error": err.to_payload() }))
+        .expect("Failed to serialize error envelope")
+}
+
+fn resolve_key_indices(headers: &[String], key_columns: &[String], side: &str) -> AppResult<Vec<usize>> {
+    let mut indices = Vec::with_capacity(key_columns.len());
+    for key in key_columns {
+        match headers.iter().position(|h| h == key) {
+            Some(idx) => indices.push(idx),
+            None => {
+                return Err(AppError::KeyNotFound {
+                    side: side.to_string(),
+                    key: key.clone(),
+                    available_headers: headers.to_vec(),
+                })
+            }
+        }
+    }
+    Ok(indices)
+}
+
+/// Rejects rows with more columns than `headers`, which would otherwise
+/// silently shift data into the wrong column further down the pipeline.
+/// Rows with fewer columns are fine; a missing trailing cell reads as an
+/// empty string. `compare_files` pads its output rows; `split_file` passes
+/// rows through as given.
+fn validate_ragged_rows(headers: &[String], rows: &[Vec<String>], side: &str) -> AppResult<()> {
+    let expected = headers.len();
+    for (i, row) in rows.iter().enumerate() {
+        if row.len() > expected {
+            return Err(AppError::RaggedRows(format!(
+                "{} row {} has {} columns, but {} headers were provided",
+                side, i, row.len(), expected
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn composite_key(row: &[String], key_indices: &[usize], options: &CompareOptions) -> String {
+    let mut parts = Vec::with_capacity(key_indices.len());
+    for &idx in key_indices {
+        let val = row.get(idx).map(|s| s.as_str()).unwrap_or("");
+        parts.push(normalize_key(val, options));
+    }
+    parts.join(&KEY_SEPARATOR.to_string())
+}
+
+/// Replaces any run of whitespace with a single space. `char::is_whitespace`
+/// treats `\r` and `\n` as whitespace, so a `\r\n` embedded in a pasted cell
+/// collapses the same as a run of plain spaces.
+fn collapse_whitespace(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut last_was_space = false;
+    for c in s.chars() {
+        if c.is_whitespace() {
+            if !last_was_space {
+                out.push(' ');
+            }
+            last_was_space = true;
+        } else {
+            out.push(c);
+            last_was_space = false;
+        }
+    }
+    out
+}
+
+/// Folds zenkaku<->hankaku width variants (full-width ASCII, the
+/// ideographic space, half-width katakana) to their canonical form, via
+/// NFKC applied only to maximal runs of width-variant characters. Running
+/// NFKC over just these runs (rather than the whole string) keeps
+/// half-width katakana + a trailing dakuten/handakuten mark combining
+/// correctly, while leaving unrelated characters (kanji, hiragana, accented
+/// Latin) untouched by NFKC's broader compatibility decomposition.
+fn fold_width(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut run = String::new();
+    for c in s.chars() {
+        let in_run = matches!(c as u32, 0xFF01..=0xFF5E | 0x3000 | 0xFF61..=0xFF9F);
+        if in_run {
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            out.extend(run.nfkc());
+            run.clear();
+        }
+        out.push(c);
+    }
+    if !run.is_empty() {
+        out.extend(run.nfkc());
+    }
+    out
+}
+
 fn normalize_key(key: &str, options: &CompareOptions) -> String {
     let mut normalized = key.to_string();
     if options.trim {
         normalized = normalized.trim().to_string();
     }
+    if options.collapse_whitespace {
+        normalized = collapse_whitespace(&normalized);
+    }
+    if options.nfkc {
+        normalized = normalized.nfkc().collect();
+    }
+    if options.fold_width {
+        normalized = fold_width(&normalized);
+    }
     if options.case_insensitive {
         normalized = normalized.to_lowercase();
     }
     normalized
 }
 
+/// Plain Levenshtein edit distance over chars (not bytes), so multi-byte
+/// text (e.g. Japanese) isn't over-counted.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (n, m) = (a.len(), b.len());
+    if n == 0 {
+        return m;
+    }
+    if m == 0 {
+        return n;
+    }
+    let mut prev: Vec<usize> = (0..=m).collect();
+    let mut curr: Vec<usize> = vec![0; m + 1];
+    for i in 1..=n {
+        curr[0] = i;
+        for j in 1..=m {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[m]
+}
+
+/// Allowed typo budget for a fuzzy key match, scaled by key length when
+/// `auto` is set, otherwise a fixed single-typo budget.
+fn fuzzy_threshold(key: &str, auto: bool) -> usize {
+    if !auto {
+        return 1;
+    }
+    match key.chars().count() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
+/// A BK-tree over normalized keys, for fast bounded edit-distance lookup.
+/// Each node's children are indexed by their integer edit distance to the
+/// parent; a query for `target` within `max_dist` only recurses into
+/// children whose edge label lies in `[dist-max_dist, dist+max_dist]`.
+struct BkNode {
+    key: String,
+    children: std::collections::HashMap<usize, BkNode>,
+}
+
+struct BkTree {
+    root: Option<BkNode>,
+}
+
+impl BkTree {
+    fn new() -> Self {
+        BkTree { root: None }
+    }
+
+    fn insert(&mut self, key: String) {
+        match &mut self.root {
+            None => {
+                self.root = Some(BkNode {
+                    key,
+                    children: std::collections::HashMap::new(),
+                })
+            }
+            Some(root) => Self::insert_node(root, key),
+        }
+    }
+
+    fn insert_node(node: &mut BkNode, key: String) {
+        let dist = levenshtein(&node.key, &key);
+        if dist == 0 {
+            return;
+        }
+        match node.children.get_mut(&dist) {
+            Some(child) => Self::insert_node(child, key),
+            None => {
+                node.children.insert(
+                    dist,
+                    BkNode {
+                        key,
+                        children: std::collections::HashMap::new(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Returns every indexed key within `max_dist` of `target`, paired with
+    /// its distance.
+    fn query(&self, target: &str, max_dist: usize) -> Vec<(String, usize)> {
+        let mut matches = Vec::new();
+        if let Some(root) = &self.root {
+            Self::query_node(root, target, max_dist, &mut matches);
+        }
+        matches
+    }
+
+    fn query_node(node: &BkNode, target: &str, max_dist: usize, out: &mut Vec<(String, usize)>) {
+        let dist = levenshtein(&node.key, target);
+        if dist <= max_dist {
+            out.push((node.key.clone(), dist));
+        }
+        let lo = dist.saturating_sub(max_dist);
+        let hi = dist + max_dist;
+        for (&edge, child) in &node.children {
+            if edge >= lo && edge <= hi {
+                Self::query_node(child, target, max_dist, out);
+            }
+        }
+    }
+}
+
+fn push_meta(
+    row: &mut Vec<String>,
+    match_status: &str,
+    diff_cols: &str,
+    dup_key_flag: &str,
+    key_distance: &str,
+    ambiguous_flag: &str,
+) {
+    row.push(match_status.to_string());
+    row.push(diff_cols.to_string());
+    row.push(dup_key_flag.to_string());
+    row.push(key_distance.to_string());
+    row.push(ambiguous_flag.to_string());
+}
+
+fn padded(row: &[String], width: usize) -> Vec<String> {
+    let mut row = row.to_vec();
+    row.resize(width, String::new());
+    row
+}
+
+fn left_padded_row(left_row: &[String], total_width: usize) -> Vec<String> {
+    padded(left_row, total_width)
+}
+
+fn right_padded_row(left_width: usize, right_row: &[String], total_width: usize) -> Vec<String> {
+    let mut row: Vec<String> = Vec::with_capacity(total_width);
+    row.resize(left_width, String::new());
+    row.extend(right_row.iter().cloned());
+    row.resize(total_width, String::new());
+    row
+}
+
+fn diff_cols_for(
+    left_headers: &[String],
+    left_row: &[String],
+    right_headers: &[String],
+    right_row: &[String],
+    key_columns: &std::collections::HashSet<String>,
+) -> String {
+    let mut diff_cols: Vec<String> = Vec::new();
+    for (i, left_header) in left_headers.iter().enumerate() {
+        if key_columns.contains(left_header) {
+            continue;
+        }
+        if let Some(right_idx) = right_headers.iter().position(|h| h == left_header) {
+            let left_val = left_row.get(i).map(|s| s.as_str()).unwrap_or("");
+            let right_val = right_row.get(right_idx).map(|s| s.as_str()).unwrap_or("");
+            if left_val != right_val {
+                diff_cols.push(left_header.clone());
+            }
+        }
+    }
+    diff_cols.join(",")
+}
+
+/// Whether the left side's value should win for a matched row pair, under
+/// strategies that decide per-row rather than per-column. Irrelevant for
+/// `PreferNonEmpty`, which decides per column instead.
+fn row_prefers_left(
+    strategy: &MergeStrategy,
+    left_headers: &[String],
+    left_row: &[String],
+    right_headers: &[String],
+    right_row: &[String],
+) -> bool {
+    match strategy {
+        MergeStrategy::PreferLeft | MergeStrategy::PreferNonEmpty => true,
+        MergeStrategy::PreferRight => false,
+        MergeStrategy::PreferNewerBy(col) => {
+            let left_val = left_headers.iter().position(|h| h == col)
+                .and_then(|i| left_row.get(i)).map(|s| s.as_str()).unwrap_or("");
+            let right_val = right_headers.iter().position(|h| h == col)
+                .and_then(|i| right_row.get(i)).map(|s| s.as_str()).unwrap_or("");
+            right_val <= left_val
+        }
+    }
+}
+
+fn resolve_shared_cell(strategy: &MergeStrategy, left_val: &str, right_val: &str, left_wins_row: bool) -> String {
+    match strategy {
+        MergeStrategy::PreferNonEmpty => {
+            if !left_val.is_empty() { left_val.to_string() } else { right_val.to_string() }
+        }
+        _ => {
+            if left_wins_row { left_val.to_string() } else { right_val.to_string() }
+        }
+    }
+}
+
+/// (left row, right row, origin) for one `merged`-table entry.
+type MergeSource = (Option<Vec<String>>, Option<Vec<String>>, &'static str);
+
+/// Builds the `merged` table: one column per logical field (headers unioned
+/// by name, left's order first), an `origin` column, and a row for every
+/// entry in `merge_sources` (left row, right row, origin) — matched pairs
+/// resolved per `strategy`, left-only/right-only rows carried through as-is.
+/// Ambiguous and literal-duplicate-key rows are excluded; they need manual
+/// resolution, not a guessed merge.
+fn build_merged_table(
+    left_headers: &[String],
+    right_headers: &[String],
+    strategy: &MergeStrategy,
+    merge_sources: &[MergeSource],
+) -> TableData {
+    let mut headers: Vec<String> = left_headers.to_vec();
+    for h in right_headers {
+        if !headers.contains(h) {
+            headers.push(h.clone());
+        }
+    }
+    headers.push("origin".to_string());
+
+    let mut rows: Vec<Vec<String>> = Vec::with_capacity(merge_sources.len());
+    for (left_row, right_row, origin) in merge_sources {
+        let left_wins_row = match (left_row, right_row) {
+            (Some(l), Some(r)) => row_prefers_left(strategy, left_headers, l, right_headers, r),
+            (None, _) => false,
+            (Some(_), None) => true,
+        };
+
+        let mut row: Vec<String> = Vec::with_capacity(headers.len());
+        for header in headers.iter().take(headers.len() - 1) {
+            let left_idx = left_headers.iter().position(|h| h == header);
+            let right_idx = right_headers.iter().position(|h| h == header);
+            let left_val = left_idx.and_then(|i| left_row.as_ref().and_then(|r| r.get(i))).map(|s| s.as_str()).unwrap_or("");
+            let right_val = right_idx.and_then(|i| right_row.as_ref().and_then(|r| r.get(i))).map(|s| s.as_str()).unwrap_or("");
+
+            let value = match (left_idx, right_idx) {
+                (Some(_), Some(_)) => resolve_shared_cell(strategy, left_val, right_val, left_wins_row),
+                (Some(_), None) => left_val.to_string(),
+                (None, Some(_)) => right_val.to_string(),
+                (None, None) => String::new(),
+            };
+            row.push(value);
+        }
+        row.push(origin.to_string());
+        rows.push(row);
+    }
+
+    TableData { headers, rows }
+}
+
 #[wasm_bindgen]
 pub fn compare_files(input_json: &str) -> String {
+    match compare_files_impl(input_json) {
+        Ok(output) => ok_envelope(&output),
+        Err(err) => err_envelope(err),
+    }
+}
+
+fn compare_files_impl(input_json: &str) -> AppResult<CompareOutput> {
     let input: CompareInput = serde_json::from_str(input_json)
-        .expect("Failed to parse CompareInput");
-    
-    let left_key_idx = input.left_headers.iter()
-        .position(|h| h == &input.key)
-        .expect("Key column not found in left headers");
-    let right_key_idx = input.right_headers.iter()
-        .position(|h| h == &input.key)
-        .expect("Key column not found in right headers");
+        .map_err(|e| AppError::BadJson(e.to_string()))?;
+
+    if input.left_headers.is_empty() || input.right_headers.is_empty() {
+        return Err(AppError::EmptyInput(
+            "left_headers and right_headers must both be non-empty".to_string(),
+        ));
+    }
+
+    let key_columns = input.key_columns();
+    let left_key_indices = resolve_key_indices(&input.left_headers, &key_columns, "left")?;
+    let right_key_indices = resolve_key_indices(&input.right_headers, &key_columns, "right")?;
+    validate_ragged_rows(&input.left_headers, &input.left_rows, "left")?;
+    validate_ragged_rows(&input.right_headers, &input.right_rows, "right")?;
+    let key_columns_set: std::collections::HashSet<String> = key_columns.iter().cloned().collect();
 
     // Normalize keys and build maps
     let mut left_map: std::collections::HashMap<String, Vec<usize>> = std::collections::HashMap::new();
     for (idx, row) in input.left_rows.iter().enumerate() {
-        if let Some(key_val) = row.get(left_key_idx) {
-            let normalized = normalize_key(key_val, &input.options);
-            left_map.entry(normalized).or_insert_with(Vec::new).push(idx);
-        }
+        let normalized = composite_key(row, &left_key_indices, &input.options);
+        left_map.entry(normalized).or_default().push(idx);
     }
 
     let mut right_map: std::collections::HashMap<String, Vec<usize>> = std::collections::HashMap::new();
     for (idx, row) in input.right_rows.iter().enumerate() {
-        if let Some(key_val) = row.get(right_key_idx) {
-            let normalized = normalize_key(key_val, &input.options);
-            right_map.entry(normalized).or_insert_with(Vec::new).push(idx);
-        }
+        let normalized = composite_key(row, &right_key_indices, &input.options);
+        right_map.entry(normalized).or_default().push(idx);
     }
 
     // Build result headers
@@ -114,12 +638,20 @@ pub fn compare_files(input_json: &str) -> String {
     result_headers.push("match_status".to_string());
     result_headers.push("diff_cols".to_string());
     result_headers.push("dup_key_flag".to_string());
+    result_headers.push("key_distance".to_string());
+    result_headers.push("ambiguous_flag".to_string());
+
+    let joined_width = result_headers.len() - META_COL_COUNT;
 
     let mut result_rows: Vec<Vec<String>> = Vec::new();
     let mut left_only_rows: Vec<Vec<String>> = Vec::new();
     let mut right_only_rows: Vec<Vec<String>> = Vec::new();
     let mut duplicates_rows: Vec<Vec<String>> = Vec::new();
 
+    // Inputs for the optional `merged` table: (left row, right row, origin).
+    // Ambiguous/literal-duplicate-key rows are deliberately excluded.
+    let mut merge_sources: Vec<MergeSource> = Vec::new();
+
     let mut processed_keys: std::collections::HashSet<String> = std::collections::HashSet::new();
 
     // Find duplicates first
@@ -127,11 +659,8 @@ pub fn compare_files(input_json: &str) -> String {
         if left_indices.len() > 1 {
             for &idx in left_indices {
                 let left_row = &input.left_rows[idx];
-                let mut full_row: Vec<String> = left_row.clone();
-                full_row.resize(result_headers.len() - 3, String::new());
-                full_row.push("left_only".to_string());
-                full_row.push(String::new());
-                full_row.push("1".to_string());
+                let mut full_row = left_padded_row(left_row, joined_width);
+                push_meta(&mut full_row, "left_only", "", "1", "", "0");
                 duplicates_rows.push(full_row);
             }
             processed_keys.insert(normalized_key.clone());
@@ -141,20 +670,24 @@ pub fn compare_files(input_json: &str) -> String {
         if right_indices.len() > 1 && !processed_keys.contains(normalized_key) {
             for &idx in right_indices {
                 let right_row = &input.right_rows[idx];
-                let mut full_row: Vec<String> = Vec::new();
-                full_row.resize(input.left_headers.len(), String::new());
-                full_row.extend(right_row.clone());
-                full_row.resize(result_headers.len() - 3, String::new());
-                full_row.push("right_only".to_string());
-                full_row.push(String::new());
-                full_row.push("1".to_string());
+                let mut full_row = right_padded_row(input.left_headers.len(), right_row, joined_width);
+                push_meta(&mut full_row, "right_only", "", "1", "", "0");
                 duplicates_rows.push(full_row);
             }
             processed_keys.insert(normalized_key.clone());
         }
     }
 
-    // Process matches and singles
+    // Keys already consumed by a fuzzy match (or an ambiguous fuzzy tie),
+    // so they aren't re-reported as right_only below.
+    let mut consumed_right_keys: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    // Right keys already consumed by an exact match, so the fuzzy pass below
+    // doesn't also pair them against a mistyped left key.
+    let mut exact_matched_right_keys: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    // Process exact matches and singles
+    let mut unmatched_left_keys: Vec<&String> = Vec::new();
     for (normalized_key, left_indices) in &left_map {
         if processed_keys.contains(normalized_key) {
             continue;
@@ -163,39 +696,107 @@ pub fn compare_files(input_json: &str) -> String {
 
         if let Some(right_idxs) = right_indices {
             if right_idxs.len() == 1 && left_indices.len() == 1 {
-                // Match
+                // Exact match
                 let left_row = &input.left_rows[left_indices[0]];
                 let right_row = &input.right_rows[right_idxs[0]];
 
-                let mut result_row: Vec<String> = left_row.clone();
-                result_row.extend(right_row.clone());
-
-                // Find diff cols
-                let mut diff_cols: Vec<String> = Vec::new();
-                for (i, left_header) in input.left_headers.iter().enumerate() {
-                    if let Some(right_idx) = input.right_headers.iter().position(|h| h == left_header) {
-                        let left_val = left_row.get(i).map(|s| s.as_str()).unwrap_or("");
-                        let right_val = right_row.get(right_idx).map(|s| s.as_str()).unwrap_or("");
-                        if left_val != right_val {
-                            diff_cols.push(left_header.clone());
-                        }
-                    }
-                }
+                let mut result_row = padded(left_row, input.left_headers.len());
+                result_row.extend(padded(right_row, input.right_headers.len()));
 
-                result_row.push("both".to_string());
-                result_row.push(diff_cols.join(","));
-                result_row.push("0".to_string());
+                let diff_cols = diff_cols_for(&input.left_headers, left_row, &input.right_headers, right_row, &key_columns_set);
+                push_meta(&mut result_row, "both", &diff_cols, "0", "0", "0");
                 result_rows.push(result_row);
+                merge_sources.push((Some(left_row.clone()), Some(right_row.clone()), "both"));
+                exact_matched_right_keys.insert(normalized_key.clone());
             }
         } else {
-            // Left only
+            unmatched_left_keys.push(normalized_key);
+        }
+    }
+
+    // Left keys the fuzzy pass below resolves (as a match or an ambiguous
+    // tie), so the left-only pass after it doesn't re-report them.
+    let mut fuzzy_resolved_left_keys: std::collections::HashSet<String> = std::collections::HashSet::new();
+
+    // Fuzzy pass: for each left key with no exact right match, look up the
+    // closest right key(s) within the edit-distance threshold from
+    // `fuzzy_threshold` via a BK-tree over the as-yet-unmatched right keys.
+    if input.options.fuzzy && !unmatched_left_keys.is_empty() {
+        let mut bktree = BkTree::new();
+        for normalized_key in right_map.keys() {
+            if !processed_keys.contains(normalized_key) && !exact_matched_right_keys.contains(normalized_key) {
+                bktree.insert(normalized_key.clone());
+            }
+        }
+
+        for normalized_key in unmatched_left_keys {
+            let left_indices = &left_map[normalized_key];
+            if left_indices.len() != 1 {
+                continue;
+            }
+            let left_row = &input.left_rows[left_indices[0]];
+
+            let threshold = fuzzy_threshold(normalized_key, input.options.max_typos_auto);
+            let candidates: Vec<(String, usize)> = bktree
+                .query(normalized_key, threshold)
+                .into_iter()
+                .filter(|(k, _)| !consumed_right_keys.contains(k) && !exact_matched_right_keys.contains(k))
+                .collect();
+
+            if candidates.is_empty() {
+                continue;
+            }
+            fuzzy_resolved_left_keys.insert(normalized_key.clone());
+
+            let min_dist = candidates.iter().map(|(_, d)| *d).min().unwrap();
+            let closest: Vec<&(String, usize)> = candidates.iter().filter(|(_, d)| *d == min_dist).collect();
+
+            if closest.len() > 1 {
+                // Ambiguous: multiple right keys tie at the minimum distance.
+                // Route the left row and every tied right row into duplicates
+                // rather than guessing which one is the real match.
+                let mut left_full = left_padded_row(left_row, joined_width);
+                push_meta(&mut left_full, "left_only", "", "0", &min_dist.to_string(), "1");
+                duplicates_rows.push(left_full);
+
+                for (right_key, dist) in &closest {
+                    consumed_right_keys.insert((*right_key).clone());
+                    for &idx in &right_map[right_key] {
+                        let right_row = &input.right_rows[idx];
+                        let mut right_full = right_padded_row(input.left_headers.len(), right_row, joined_width);
+                        push_meta(&mut right_full, "right_only", "", "0", &dist.to_string(), "1");
+                        duplicates_rows.push(right_full);
+                    }
+                }
+            } else {
+                let (right_key, dist) = closest[0];
+                let right_idxs = &right_map[right_key];
+                if right_idxs.len() == 1 {
+                    let right_row = &input.right_rows[right_idxs[0]];
+                    let mut result_row = padded(left_row, input.left_headers.len());
+                    result_row.extend(padded(right_row, input.right_headers.len()));
+
+                    let diff_cols = diff_cols_for(&input.left_headers, left_row, &input.right_headers, right_row, &key_columns_set);
+                    push_meta(&mut result_row, "fuzzy", &diff_cols, "0", &dist.to_string(), "0");
+                    result_rows.push(result_row);
+                    merge_sources.push((Some(left_row.clone()), Some(right_row.clone()), "fuzzy"));
+                    consumed_right_keys.insert(right_key.clone());
+                }
+            }
+        }
+    }
+
+    // Left only: left keys with no exact or fuzzy match
+    for (normalized_key, left_indices) in &left_map {
+        if processed_keys.contains(normalized_key) || fuzzy_resolved_left_keys.contains(normalized_key) {
+            continue;
+        }
+        if !right_map.contains_key(normalized_key) {
             for &idx in left_indices {
-                let mut row = input.left_rows[idx].clone();
-                row.resize(result_headers.len() - 3, String::new());
-                row.push("left_only".to_string());
-                row.push(String::new());
-                row.push("0".to_string());
+                let mut row = left_padded_row(&input.left_rows[idx], joined_width);
+                push_meta(&mut row, "left_only", "", "0", "", "0");
                 left_only_rows.push(row);
+                merge_sources.push((Some(input.left_rows[idx].clone()), None, "left_only"));
             }
         }
     }
@@ -204,21 +805,22 @@ pub fn compare_files(input_json: &str) -> String {
         if processed_keys.contains(normalized_key) {
             continue;
         }
-        if !left_map.contains_key(normalized_key) {
-            // Right only
+        if !left_map.contains_key(normalized_key) && !consumed_right_keys.contains(normalized_key) {
             for &idx in right_indices {
-                let mut row: Vec<String> = Vec::new();
-                row.resize(input.left_headers.len(), String::new());
-                row.extend(input.right_rows[idx].clone());
-                row.resize(result_headers.len() - 3, String::new());
-                row.push("right_only".to_string());
-                row.push(String::new());
-                row.push("0".to_string());
+                let row = right_padded_row(input.left_headers.len(), &input.right_rows[idx], joined_width);
+                let mut row = row;
+                push_meta(&mut row, "right_only", "", "0", "", "0");
                 right_only_rows.push(row);
+                merge_sources.push((None, Some(input.right_rows[idx].clone()), "right_only"));
             }
         }
     }
 
+    let merged = match input.merge_strategy.as_deref().and_then(MergeStrategy::parse) {
+        Some(strategy) => build_merged_table(&input.left_headers, &input.right_headers, &strategy, &merge_sources),
+        None => TableData { headers: Vec::new(), rows: Vec::new() },
+    };
+
     let output = CompareOutput {
         result: TableData {
             headers: result_headers.clone(),
@@ -236,42 +838,69 @@ pub fn compare_files(input_json: &str) -> String {
             headers: result_headers.clone(),
             rows: duplicates_rows,
         },
+        merged,
         log: vec![
             ("left_rows".to_string(), input.left_rows.len().to_string()),
             ("right_rows".to_string(), input.right_rows.len().to_string()),
-            ("key_column".to_string(), input.key.clone()),
+            ("key_columns".to_string(), key_columns.join(",")),
+            ("merge_strategy".to_string(), input.merge_strategy.clone().unwrap_or_default()),
             ("trim".to_string(), input.options.trim.to_string()),
             ("case_insensitive".to_string(), input.options.case_insensitive.to_string()),
+            ("nfkc".to_string(), input.options.nfkc.to_string()),
+            ("collapse_whitespace".to_string(), input.options.collapse_whitespace.to_string()),
+            ("fold_width".to_string(), input.options.fold_width.to_string()),
+            ("fuzzy".to_string(), input.options.fuzzy.to_string()),
+            ("max_typos_auto".to_string(), input.options.max_typos_auto.to_string()),
         ],
     };
 
-    serde_json::to_string(&output).expect("Failed to serialize CompareOutput")
+    Ok(output)
 }
 
 #[wasm_bindgen]
 pub fn split_file(input_json: &str) -> String {
+    match split_file_impl(input_json) {
+        Ok(output) => ok_envelope(&output),
+        Err(err) => err_envelope(err),
+    }
+}
+
+fn split_file_impl(input_json: &str) -> AppResult<SplitOutput> {
     let input: SplitInput = serde_json::from_str(input_json)
-        .expect("Failed to parse SplitInput");
-    
-    let key_idx = input.headers.iter()
-        .position(|h| h == &input.key)
-        .expect("Key column not found in headers");
+        .map_err(|e| AppError::BadJson(e.to_string()))?;
+
+    if input.headers.is_empty() {
+        return Err(AppError::EmptyInput("headers must be non-empty".to_string()));
+    }
+
+    let key_columns = input.key_columns();
+    let key_indices = resolve_key_indices(&input.headers, &key_columns, "input")?;
+    validate_ragged_rows(&input.headers, &input.rows, "input")?;
 
     let mut groups: std::collections::HashMap<String, Vec<Vec<String>>> = std::collections::HashMap::new();
+    let mut readable_key_values: std::collections::HashMap<String, String> = std::collections::HashMap::new();
 
     for row in input.rows {
-        let key_value = row.get(key_idx)
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .unwrap_or("EMPTY")
-            .to_string();
-        groups.entry(key_value).or_insert_with(Vec::new).push(row);
+        let trimmed: Vec<&str> = key_indices.iter()
+            .map(|&idx| row.get(idx).map(|s| s.trim()).unwrap_or(""))
+            .collect();
+
+        let readable = if trimmed.iter().all(|s| s.is_empty()) {
+            "EMPTY".to_string()
+        } else {
+            trimmed.join(" | ")
+        };
+        let group_key = trimmed.join(&KEY_SEPARATOR.to_string());
+
+        readable_key_values.entry(group_key.clone()).or_insert(readable);
+        groups.entry(group_key).or_default().push(row);
     }
 
     let mut parts: Vec<SplitPart> = groups.into_iter()
-        .map(|(key_value, rows)| {
+        .map(|(group_key, rows)| {
+            let key_value = readable_key_values.remove(&group_key).unwrap_or(group_key);
             SplitPart {
-                key_value: key_value.clone(),
+                key_value,
                 table: TableData {
                     headers: input.headers.clone(),
                     rows,
@@ -282,6 +911,5 @@ pub fn split_file(input_json: &str) -> String {
 
     parts.sort_by(|a, b| a.key_value.cmp(&b.key_value));
 
-    let output = SplitOutput { parts };
-    serde_json::to_string(&output).expect("Failed to serialize SplitOutput")
+    Ok(SplitOutput { parts })
 }